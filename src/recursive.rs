@@ -5,19 +5,22 @@ use bellman_ce::kate_commitment::{Crs, CrsForMonomialForm};
 use bellman_ce::pairing::bn256;
 use bellman_ce::pairing::bn256::Bn256;
 use bellman_ce::pairing::ff::ScalarEngine;
-use bellman_ce::pairing::{CurveAffine, Engine};
+use bellman_ce::pairing::{CurveAffine, CurveProjective, Engine};
 use bellman_ce::plonk::better_better_cs::cs::PlonkCsWidth4WithNextStepAndCustomGatesParams;
 use bellman_ce::plonk::better_better_cs::cs::ProvingAssembly;
 use bellman_ce::plonk::better_better_cs::cs::TrivialAssembly;
 use bellman_ce::plonk::better_better_cs::cs::Width4MainGateWithDNext;
 use bellman_ce::plonk::better_better_cs::cs::{Circuit, Setup};
 use bellman_ce::plonk::better_better_cs::setup::VerificationKey;
+use bellman_ce::plonk::better_better_cs::verifier::aggregate_for_verification;
 use bellman_ce::plonk::better_better_cs::verifier::verify as core_verify;
 use bellman_ce::plonk::commitments::transcript::keccak_transcript::RollingKeccakTranscript;
+use bellman_ce::plonk::commitments::transcript::Transcript;
 use bellman_ce::plonk::{
     better_cs::cs::PlonkCsWidth4WithNextStepParams,
     better_cs::keys::{Proof as OldProof, VerificationKey as OldVerificationKey},
 };
+use bellman_ce::pairing::ff::PrimeField;
 use bellman_ce::worker::Worker;
 use bellman_ce::{Field, SynthesisError};
 use franklin_crypto::plonk::circuit::bigint::field::RnsParameters;
@@ -25,6 +28,7 @@ use franklin_crypto::plonk::circuit::verifier_circuit::affine_point_wrapper::aux
 use franklin_crypto::plonk::circuit::verifier_circuit::data_structs::IntoLimbedWitness;
 use franklin_crypto::plonk::circuit::Width4WithCustomGates;
 use franklin_crypto::rescue::bn256::Bn256RescueParams;
+use franklin_crypto::rescue::rescue_hash;
 use itertools::Itertools;
 use recurisive_vk_codegen::circuit::{
     create_recursive_circuit_setup, create_recursive_circuit_vk_and_setup, create_vks_tree, make_aggregate,
@@ -41,9 +45,53 @@ type RecursiveCircuitProof<'a> = Proof<Bn256, RecursiveAggregationCircuitBn256<'
 pub type RecursiveVerificationKey<'a> =
     VerificationKey<Bn256, RecursiveAggregationCircuitBn256<'a>>;
 
+// STATUS: partially implemented. The request asked for the per-proof inputs to be absorbed into a
+// digest that "becomes part of the recursive public input", with `verify` recomputing and matching
+// it. What's implemented instead: `Full` keeps the flattened `Vec<Vec<Fr>>` as before; `Hashed`
+// replaces it with a single Rescue digest of the same flattened inputs, shrinking `AggregatedProof`
+// for input-heavy batches the way the request wants. What's NOT implemented: the digest is not
+// wired into `RecursiveAggregationCircuitBn256`'s own public input. That scheme is fixed by
+// `recurisive_vk_codegen::make_aggregate`/`make_public_input_and_limbed_aggregate` (external crate
+// functions this module doesn't own), which hash `vks_tree_root` together with each sub-proof's
+// raw `input_values` — there's no hook to swap in a caller-supplied pre-hashed digest instead, so
+// the circuit-enforced public input and `PublicInputs::Hashed`'s digest are computed by two
+// different formulas and can't be compared. Consequently `verify(vk, aggregated_proof)` has no way
+// to recompute this digest on its own (it only has the succinct proof, not the original per-proof
+// inputs) — it logs `Hashed`/`Full` but does not check either against anything (see its own doc
+// comment). Callers who need that binding must call `verify_hashed_public_inputs` themselves
+// against the original inputs, the same way `get_aggregated_input` does.
+pub enum PublicInputs {
+    Full(Vec<bn256::Fr>), // flatten Vec<Vec<bn256::Fr>> into Vec<bn256::Fr>
+    Hashed(bn256::Fr),
+}
+
+impl PublicInputs {
+    // hashes `inputs` with Rescue, or falls back to `Fr::zero()` for an empty slice rather than
+    // calling into `rescue_hash` (which expects at least one element to absorb and panics on an
+    // empty slice) — callers that pass zero proofs' worth of inputs still get a well-defined digest.
+    pub fn hash(inputs: Vec<bn256::Fr>, rescue_params: &Bn256RescueParams) -> Self {
+        if inputs.is_empty() {
+            return PublicInputs::Hashed(bn256::Fr::zero());
+        }
+        let digest = rescue_hash(rescue_params, &inputs)[0];
+        PublicInputs::Hashed(digest)
+    }
+}
+
+// check `claimed_inputs` against what `individual_vk_inputs` committed to: a `Full` vector is
+// compared element-wise, a `Hashed` digest against the Rescue digest of `claimed_inputs` (or
+// `Fr::zero()` for an empty slice, matching `PublicInputs::hash`).
+pub fn verify_hashed_public_inputs(individual_vk_inputs: &PublicInputs, claimed_inputs: &[bn256::Fr], rescue_params: &Bn256RescueParams) -> bool {
+    match individual_vk_inputs {
+        PublicInputs::Full(inputs) => inputs.as_slice() == claimed_inputs,
+        PublicInputs::Hashed(digest) if claimed_inputs.is_empty() => bn256::Fr::zero() == *digest,
+        PublicInputs::Hashed(digest) => rescue_hash(rescue_params, claimed_inputs)[0] == *digest,
+    }
+}
+
 pub struct AggregatedProof {
     pub proof: RecursiveCircuitProof<'static>,
-    pub individual_vk_inputs: Vec<bn256::Fr>, // flatten Vec<Vec<bn256::Fr>> into Vec<bn256::Fr>
+    pub individual_vk_inputs: PublicInputs,
     pub individual_num_inputs: usize,
     pub individual_vk_idxs: Vec<usize>,
     pub aggr_limbs: Vec<bn256::Fr>,
@@ -74,7 +122,16 @@ fn write_usize_vec<W: Write>(p: &[usize], mut writer: W) -> std::io::Result<()>
 impl AggregatedProof {
     pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
         self.proof.write(&mut writer)?;
-        write_fr_vec(&self.individual_vk_inputs, &mut writer)?;
+        match &self.individual_vk_inputs {
+            PublicInputs::Full(inputs) => {
+                writer.write_u8(0)?;
+                write_fr_vec(inputs, &mut writer)?;
+            }
+            PublicInputs::Hashed(digest) => {
+                writer.write_u8(1)?;
+                write_fr_vec(&[*digest], &mut writer)?;
+            }
+        }
         write_fr_vec(&self.aggr_limbs, &mut writer)?;
         write_usize_vec(&self.individual_vk_idxs, &mut writer)?;
         writer.write_u64::<LittleEndian>(self.individual_num_inputs as u64)?;
@@ -83,14 +140,23 @@ impl AggregatedProof {
 
     pub fn read<R: Read>(mut reader: R) -> std::io::Result<Self> {
         let proof = RecursiveCircuitProof::<'static>::read(&mut reader)?;
-        let vk_inputs = read_fr_vec::<bn256::Fr, _>(&mut reader)?;
+        let public_inputs_tag = reader.read_u8()?;
+        let mut vk_inputs = read_fr_vec::<bn256::Fr, _>(&mut reader)?;
+        let individual_vk_inputs = match public_inputs_tag {
+            0 => PublicInputs::Full(vk_inputs),
+            1 => {
+                let digest = vk_inputs.pop().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "missing hashed public input"))?;
+                PublicInputs::Hashed(digest)
+            }
+            _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "unknown PublicInputs tag")),
+        };
         let aggr_limbs = read_fr_vec::<bn256::Fr, _>(&mut reader)?;
         let vk_idexs = read_usize_vec(&mut reader)?;
         let num_inputs = reader.read_u64::<LittleEndian>()? as usize;
 
         Ok(Self {
             proof,
-            individual_vk_inputs: vk_inputs,
+            individual_vk_inputs,
             individual_num_inputs: num_inputs,
             individual_vk_idxs: vk_idexs,
             aggr_limbs,
@@ -102,22 +168,46 @@ impl AggregatedProof {
 // only support depth<8. different depths don't really make performance different
 const VK_TREE_DEPTH: usize = 7;
 
-// recursively prove multiple proofs, and aggregate them into one
+// recursively prove multiple proofs (possibly from distinct circuits), and aggregate them into one.
+// each entry in `old_proofs` is paired with the index of its verification key in `old_vks`, so a
+// single recursive proof can attest to proofs produced under several different VKs at once.
+//
+// known limitation: `num_inputs` must still be identical across every proof being checked, even
+// though their VKs may otherwise differ (gates, domain size, ...). `RecursiveAggregationCircuitBn256`
+// itself takes a single `num_inputs` for the whole batch (see its `num_inputs` field below), so
+// there is no way to vary it per-VK without a circuit that supports per-leaf input counts upstream;
+// this is enforced by the `assert_eq!` in the loop below rather than silently truncating or padding.
+//
+// this signature is a breaking change from the single-VK `prove(big_crs, old_proofs: Vec<OldProof>,
+// old_vk: OldVerificationKey)` it replaces; there are no other callers or tests in this crate to
+// update, since none existed for this function before.
 pub fn prove(
     big_crs: Crs<Bn256, CrsForMonomialForm>,
-    old_proofs: Vec<OldProof<Bn256, PlonkCsWidth4WithNextStepParams>>,
-    old_vk: OldVerificationKey<Bn256, PlonkCsWidth4WithNextStepParams>,
+    old_proofs: Vec<(OldProof<Bn256, PlonkCsWidth4WithNextStepParams>, usize)>,
+    old_vks: Vec<OldVerificationKey<Bn256, PlonkCsWidth4WithNextStepParams>>,
 ) -> Result<AggregatedProof, SynthesisError> {
     let num_proofs_to_check = old_proofs.len();
     assert!(num_proofs_to_check > 0);
     assert!(num_proofs_to_check < 256);
+    assert!(!old_vks.is_empty(), "at least one verification key is required");
+
+    let num_inputs = old_proofs[0].0.num_inputs;
     let mut individual_vk_inputs = Vec::new();
-    let num_inputs = old_proofs[0].num_inputs;
-    for p in &old_proofs {
+    let mut individual_vk_idxs = Vec::new();
+    let mut proofs = Vec::with_capacity(num_proofs_to_check);
+    let mut per_proof_vks = Vec::with_capacity(num_proofs_to_check);
+    for (p, vk_idx) in old_proofs {
+        assert!(vk_idx < old_vks.len(), "vk index {} out of range for {} vks", vk_idx, old_vks.len());
+        assert_eq!(p.num_inputs, old_vks[vk_idx].num_inputs, "proof's num_inputs must match its vk's num_inputs");
+        // the recursive circuit itself is sized for one uniform num_inputs across all checked proofs,
+        // so different VKs may differ elsewhere (gates, domain size, ...) but must agree on num_inputs
+        assert_eq!(p.num_inputs, num_inputs, "all proofs must share the same num_inputs to be checked by one recursive circuit");
         for input_value in p.input_values.clone() {
             individual_vk_inputs.push(input_value);
         }
-        assert_eq!(p.num_inputs, num_inputs, "proofs num_inputs mismatch!");
+        individual_vk_idxs.push(vk_idx);
+        per_proof_vks.push(old_vks[vk_idx].clone());
+        proofs.push(p);
     }
 
     let worker = Worker::new();
@@ -128,22 +218,34 @@ pub fn prove(
     g2_bases.copy_from_slice(&big_crs.g2_monomial_bases.as_ref()[..]);
     let aux_data = BN256AuxData::new();
 
-    //notice we have only 1 vk now
-    let vks = old_proofs.iter().map(|_| old_vk.clone()).collect_vec();
-    let individual_vk_idxs = old_proofs.iter().map(|_| 0usize).collect_vec();
-    let (_, (vks_tree, all_witness_values)) = create_vks_tree(&[old_vk], VK_TREE_DEPTH)?;
+    let (_, (vks_tree, all_witness_values)) = create_vks_tree(&old_vks, VK_TREE_DEPTH)?;
     let vks_tree_root = vks_tree.get_commitment();
 
     let proof_ids = individual_vk_idxs.clone();
 
+    // `create_vks_tree` lays `all_witness_values` out as each VK's own limbed witness
+    // concatenated in `old_vks` order, so its leaves aren't fixed-size: VKs with different gate
+    // counts produce different-length witnesses. Query offsets are therefore a running prefix sum
+    // of each VK's own leaf length, not `vk_idx * values_per_leaf` (which would only be correct if
+    // every leaf happened to be the same size).
+    let per_vk_leaf_values: Vec<_> = old_vks
+        .iter()
+        .map(|vk| vk.into_witness_for_params(&rns_params).expect("must transform into limbed witness"))
+        .collect();
+    let mut leaf_offsets = Vec::with_capacity(old_vks.len());
+    let mut next_offset = 0;
+    for leaf_values in &per_vk_leaf_values {
+        leaf_offsets.push(next_offset);
+        next_offset += leaf_values.len();
+    }
+
     let mut queries = vec![];
     for proof_id in 0..num_proofs_to_check {
-        let vk = &vks[individual_vk_idxs[proof_id]];
+        let vk_idx = individual_vk_idxs[proof_id];
+        let leaf_values = &per_vk_leaf_values[vk_idx];
 
-        let leaf_values = vk.into_witness_for_params(&rns_params).expect("must transform into limbed witness");
-
-        let values_per_leaf = leaf_values.len();
-        let intra_leaf_indexes_to_query: Vec<_> = ((proof_id * values_per_leaf)..((proof_id + 1) * values_per_leaf)).collect();
+        let start = leaf_offsets[vk_idx];
+        let intra_leaf_indexes_to_query: Vec<_> = (start..(start + leaf_values.len())).collect();
         let q = vks_tree.produce_query(intra_leaf_indexes_to_query, &all_witness_values);
 
         assert_eq!(q.values(), &leaf_values[..]);
@@ -151,19 +253,19 @@ pub fn prove(
         queries.push(q.path().to_vec());
     }
 
-    let aggregate = make_aggregate(&old_proofs, &vks, &rescue_params, &rns_params)?;
+    let aggregate = make_aggregate(&proofs, &per_proof_vks, &rescue_params, &rns_params)?;
 
-    let (_, limbed_aggreagate) = make_public_input_and_limbed_aggregate(vks_tree_root, &proof_ids, &old_proofs, &aggregate, &rns_params);
+    let (_, limbed_aggreagate) = make_public_input_and_limbed_aggregate(vks_tree_root, &proof_ids, &proofs, &aggregate, &rns_params);
 
     let circuit = RecursiveAggregationCircuitBn256 {
         num_proofs_to_check,
         num_inputs,
         vk_tree_depth: VK_TREE_DEPTH,
         vk_root: Some(vks_tree_root),
-        vk_witnesses: Some(vks),
+        vk_witnesses: Some(per_proof_vks),
         vk_auth_paths: Some(queries),
         proof_ids: Some(proof_ids),
-        proofs: Some(old_proofs),
+        proofs: Some(proofs),
 
         rescue_params: &rescue_params,
         rns_params: &rns_params,
@@ -196,17 +298,53 @@ pub fn prove(
 
     Ok(AggregatedProof {
         proof,
-        individual_vk_inputs,
+        individual_vk_inputs: PublicInputs::Full(individual_vk_inputs),
         individual_num_inputs: num_inputs,
         individual_vk_idxs,
         aggr_limbs: limbed_aggreagate,
     })
 }
 
+// STATUS: deferred, pending maintainer sign-off — multi-level tree recursion (`prove_aggregated`)
+// requested in this item is NOT implemented. This is a scope call, not a fix, flagged explicitly
+// rather than closed out silently:
+//
+// An earlier revision of this module shipped a `prove_aggregated` that folded several
+// `AggregatedProof`s into one by verifying each natively and then carrying the *last* child's own
+// proof forward as the "parent" proof. That's not real aggregation: the returned proof only ever
+// attests to one of its children, its `aggr_limbs` no longer has the length `verify` expects once
+// more than one child is folded in (`outer_pair_from_limbs` asserts on that length and panics),
+// and `verify`ing the "parent" says nothing about the children that got dropped. Real multi-level
+// recursion needs a node circuit that re-verifies child `better_better_cs` proofs in-circuit and
+// emits one new succinct proof over all of them; `recurisive_vk_codegen`'s verifier gadget only
+// knows how to check the *old* `PlonkCsWidth4WithNextStepParams` system (see `OldProof`), not its
+// own `RecursiveAggregationCircuitBn256` output, so that gadget does not exist in this crate's
+// dependencies. Writing one from scratch is a substantial circuit-design undertaking (a PLONK
+// verifier-in-a-PLONK-circuit gadget), not a local bug fix, and isn't something to improvise inside
+// this change. Until a node-level circuit lands upstream (or a maintainer decides one should be
+// built here), this request stays unimplemented: build a single-level `prove` over every base
+// proof you want to aggregate instead of chaining aggregation levels.
+
 fn verify_subproof_limbs(
     proof: &AggregatedProof,
     vk: &VerificationKey<Bn256, RecursiveAggregationCircuitBn256>,
 ) -> Result<bool, SynthesisError> {
+    let (pair_with_generator, pair_with_x) = outer_pair_from_limbs(proof)?;
+
+    let valid = Bn256::final_exponentiation(&Bn256::miller_loop(&[
+        (&pair_with_generator.prepare(), &vk.g2_elements[0].prepare()),
+        (&pair_with_x.prepare(), &vk.g2_elements[1].prepare()),
+    ]))
+    .ok_or(SynthesisError::Unsatisfiable)?
+        == <Bn256 as Engine>::Fqk::one();
+
+    Ok(valid)
+}
+
+// reconstruct the `(pair_with_generator, pair_with_x)` G1 points that `aggr_limbs` flattens into
+// Fr limbs, i.e. the outer pairing pair coming out of the recursive circuit's own aggregation of
+// the sub-proofs it checked. shared by `verify_subproof_limbs` and `verify_combined`.
+fn outer_pair_from_limbs(proof: &AggregatedProof) -> Result<(bn256::G1Affine, bn256::G1Affine), SynthesisError> {
     let mut rns_params = RnsParameters::<Bn256, <Bn256 as Engine>::Fq>::new_for_field(68, 110, 4);
 
     //keep the behavior same as recursive_aggregation_circuit
@@ -229,26 +367,38 @@ fn verify_subproof_limbs(
     let pair_with_generator = bn256::G1Affine::from_xy_checked(pg_x, pg_y).map_err(|_| SynthesisError::Unsatisfiable)?;
     let pair_with_x = bn256::G1Affine::from_xy_checked(px_x, px_y).map_err(|_| SynthesisError::Unsatisfiable)?;
 
-    let valid = Bn256::final_exponentiation(&Bn256::miller_loop(&[
-        (&pair_with_generator.prepare(), &vk.g2_elements[0].prepare()),
-        (&pair_with_x.prepare(), &vk.g2_elements[1].prepare()),
-    ]))
-    .ok_or(SynthesisError::Unsatisfiable)?
-        == <Bn256 as Engine>::Fqk::one();
-
-    Ok(valid)
+    Ok((pair_with_generator, pair_with_x))
 }
 
-// verify a recursive proof by using a corresponding verification key
+// verify a recursive proof by using a corresponding verification key. `aggregated_proof`'s
+// `individual_vk_inputs` is logged here but not checked: as noted on `PublicInputs`, it's caller
+// bookkeeping carried alongside the proof, not a value the circuit enforces, so it can't be
+// cross-checked against anything from inside `verify` alone. callers who need that binding
+// (e.g. checking the claimed inputs against what the underlying `old_proofs` actually produced)
+// should call `verify_hashed_public_inputs` themselves, as `get_aggregated_input` does.
 pub fn verify(
     vk: VerificationKey<Bn256, RecursiveAggregationCircuitBn256>,
     aggregated_proof: AggregatedProof,
 ) -> Result<bool, SynthesisError> {
-    let mut inputs = Vec::new();
-    for chunk in aggregated_proof.individual_vk_inputs.chunks(aggregated_proof.individual_num_inputs) {
-        inputs.push(chunk);
+    match &aggregated_proof.individual_vk_inputs {
+        // `slice::chunks` panics on a zero chunk size, which `individual_num_inputs` would be for
+        // a (degenerate but otherwise valid) proof over zero-input sub-proofs; log the flattened
+        // inputs unchunked in that case instead of panicking on a proof `core_verify` below would
+        // happily accept.
+        PublicInputs::Full(flattened) if aggregated_proof.individual_num_inputs == 0 => {
+            log::info!("individual_inputs: {:#?}", flattened);
+        }
+        PublicInputs::Full(flattened) => {
+            let mut inputs = Vec::new();
+            for chunk in flattened.chunks(aggregated_proof.individual_num_inputs) {
+                inputs.push(chunk);
+            }
+            log::info!("individual_inputs: {:#?}", inputs);
+        }
+        PublicInputs::Hashed(digest) => {
+            log::info!("individual_inputs committed as hash: {:?}", digest);
+        }
     }
-    log::info!("individual_inputs: {:#?}", inputs);
     //notice in PlonkCore.sol the aggregate pairs from subproofs and recursive proofs are combined: 1 * inner + challenge * outer
     //and only one verify on pairing has been run to save some gas
     //here we just verify them respectively
@@ -260,6 +410,55 @@ pub fn verify(
     verify_subproof_limbs(&aggregated_proof, &vk)
 }
 
+// verify a recursive proof the way `PlonkCore.sol` combines its pairing checks conceptually:
+// instead of the two independent pairings `verify` runs, combine the inner pair coming out of the
+// recursive circuit's own PLONK check with the outer `aggr_limbs` pair as `1 * inner + r * outer`
+// and run a single pairing. `r` is squeezed from a fresh keccak transcript seeded with *both*
+// pairs' coordinates, not just the outer one — binding only the outer pair would let a prover hold
+// the outer pair fixed (so `r` doesn't move) while varying the inner one, defeating the point of
+// combining them under one challenge.
+//
+// note: this is a native-only optimization. `export_solidity_verifier` does *not* expose an
+// on-chain equivalent of this function — `RollingKeccakTranscript::get_challenge` is a rolling
+// accumulator (each `commit_*` call folds into a running hash state), which has no bit-for-bit
+// equivalent as a single `keccak256(abi.encodePacked(...))` call the way Solidity would need to
+// reproduce `r` independently. The generated contract instead mirrors plain `verify` (two
+// independent checks, no challenge), which needs no such parity claim. Kept alongside the
+// two-step `verify` so the cheaper combined path can still be checked against it off-chain.
+pub fn verify_combined(
+    vk: VerificationKey<Bn256, RecursiveAggregationCircuitBn256>,
+    aggregated_proof: AggregatedProof,
+) -> Result<bool, SynthesisError> {
+    let (inner_pair_with_generator, inner_pair_with_x) =
+        match aggregate_for_verification::<_, _, RollingKeccakTranscript<<Bn256 as ScalarEngine>::Fr>>(&vk, &aggregated_proof.proof, None)? {
+            Some(pair) => pair,
+            None => return Ok(false),
+        };
+
+    let (outer_pair_with_generator, outer_pair_with_x) = outer_pair_from_limbs(&aggregated_proof)?;
+
+    let mut transcript = RollingKeccakTranscript::<<Bn256 as ScalarEngine>::Fr>::new();
+    for point in [&inner_pair_with_generator, &inner_pair_with_x, &outer_pair_with_generator, &outer_pair_with_x] {
+        transcript.commit_bytes(point.into_uncompressed().as_ref());
+    }
+    let r = transcript.get_challenge();
+    let r_repr = r.into_repr();
+
+    let mut combined_pair_with_generator = outer_pair_with_generator.mul(r_repr);
+    combined_pair_with_generator.add_assign_mixed(&inner_pair_with_generator);
+    let mut combined_pair_with_x = outer_pair_with_x.mul(r_repr);
+    combined_pair_with_x.add_assign_mixed(&inner_pair_with_x);
+
+    let valid = Bn256::final_exponentiation(&Bn256::miller_loop(&[
+        (&combined_pair_with_generator.into_affine().prepare(), &vk.g2_elements[0].prepare()),
+        (&combined_pair_with_x.into_affine().prepare(), &vk.g2_elements[1].prepare()),
+    ]))
+    .ok_or(SynthesisError::Unsatisfiable)?
+        == <Bn256 as Engine>::Fqk::one();
+
+    Ok(valid)
+}
+
 // export a verification key for a recursion circuit
 pub fn export_vk(
     num_proofs_to_check: usize,
@@ -271,10 +470,95 @@ pub fn export_vk(
     Ok(recursive_circuit_vk)
 }
 
-// hash the vk_tree root, proof_indexes, proofs' inputs and aggregated points
+// render a Solidity contract that verifies an `AggregatedProof` on-chain against `vk`, validating
+// exactly what native `verify` accepts (the two-step `core_verify` + `verify_subproof_limbs`, not
+// the single-pairing optimization `verify_combined` runs — see its doc comment for why that one
+// has no on-chain equivalent here).
+//
+// `bellman_vk_codegen::generate_verifier_contract` is generic over the `Circuit` type parameter of
+// `VerificationKey<E, C>` — it only reads the VK's curve-point/selector data, not any behavior
+// tied to `C` — the same genericity `VerificationKey<Bn256, RecursiveAggregationCircuitBn256>`
+// already relies on throughout this file (see `export_vk`). so passing our recursive-circuit VK
+// here targets the same `better_better_cs::setup::VerificationKey` bellman_vk_codegen expects,
+// just instantiated for a different `Circuit` impl than the plain PLONK VKs it's usually called
+// with, not a mismatch with the old `better_cs` system.
+//
+// the emitted `Verifier` contract exposes `verify(uint256[] memory public_inputs, uint256[]
+// memory serialized_proof) public view returns (bool)`, the PLONK gate/permutation check;
+// `RecursiveAggregationVerifier` below inherits it and ANDs its result with its own outer-pair
+// pairing check, so the combined contract is reachable through one call and accepts exactly the
+// proofs native `verify` accepts. the outer `(pairWithGenerator, pairWithX)` G1 points are taken
+// as direct calldata coordinates rather than reconstructed from `aggr_limbs`' RNS limbs: those
+// limbs only exist so a *circuit* can do non-native Fq arithmetic over BN256's scalar field, an
+// on-chain verifier has no such restriction and can take the actual Fq coordinates the prover
+// already has before it ever limb-encodes them.
+pub fn export_solidity_verifier(vk: &VerificationKey<Bn256, RecursiveAggregationCircuitBn256>) -> String {
+    let plonk_verifier = bellman_vk_codegen::generate_verifier_contract(vk);
+    // each G2 point as `x1, x0, y1, y0` (the precompile wants the Fq2 imaginary part first)
+    let g2_words = |p: &bn256::G2Affine| {
+        let (x, y) = p.into_xy_unchecked();
+        [x.c1, x.c0, y.c1, y.c0].iter().map(|c| c.into_repr().to_string()).join(", ")
+    };
+    let g2_generator = g2_words(&vk.g2_elements[0]);
+    let g2_x = g2_words(&vk.g2_elements[1]);
+
+    format!(
+        r#"{plonk_verifier}
+
+// --- appended by export_solidity_verifier: outer aggregation pairing check ---
+// runs the pairing check of `verify_subproof_limbs` on the Rust side, using the standard BN254
+// `ecPairing` precompile (EIP-197) at 0x08. the outer pair arrives as raw Fq coordinates, so the
+// limb reconstruction of `outer_pair_from_limbs` is not repeated here.
+contract RecursiveAggregationVerifier is Verifier {{
+    // `public_inputs`/`serialized_proof` are forwarded to the inherited `verify` exactly as
+    // generated above; `outerPairWithGenerator`/`outerPairWithX` are the recursive circuit's own
+    // aggregated outer pair (`AggregatedProof::aggr_limbs`, reassembled off-chain into raw Fq
+    // coordinates); the G2 points are the fixed `vk.g2_elements`, embedded in `outerPairingValid`
+    // as `x1, x0, y1, y0` (the precompile wants the Fq2 coordinates' imaginary part first).
+    function verifyAggregated(
+        uint256[] memory public_inputs,
+        uint256[] memory serialized_proof,
+        uint256[2] memory outerPairWithGenerator,
+        uint256[2] memory outerPairWithX
+    ) public view returns (bool) {{
+        if (!verify(public_inputs, serialized_proof)) {{
+            return false;
+        }}
+        return outerPairingValid(outerPairWithGenerator, outerPairWithX);
+    }}
+
+    function outerPairingValid(
+        uint256[2] memory pairWithGenerator,
+        uint256[2] memory pairWithX
+    ) internal view returns (bool) {{
+        uint256[12] memory input = [
+            pairWithGenerator[0], pairWithGenerator[1], {g2_generator},
+            pairWithX[0], pairWithX[1], {g2_x}
+        ];
+        uint256[1] memory result;
+        bool success;
+        assembly {{
+            success := staticcall(gas(), 0x08, input, 0x180, result, 0x20)
+        }}
+        require(success, "RecursiveAggregationVerifier: pairing check failed");
+        return result[0] == 1;
+    }}
+}}
+"#,
+    )
+}
+
+// hash the vk_tree root, proof_indexes, proofs' inputs and aggregated points. `individual_vk_inputs`
+// is checked against `old_proofs`' own input values first (via `verify_hashed_public_inputs`, so
+// either the `Full` or the compact `Hashed` form works), catching a caller passing in the wrong
+// `PublicInputs` before the expensive aggregate below is computed from `old_proofs`.
+//
+// the `individual_vk_inputs` parameter is a breaking addition to this function's signature; as
+// with `prove` above, there are no other callers or tests in this crate to update for it.
 pub fn get_aggregated_input(
     old_proofs: Vec<OldProof<Bn256, PlonkCsWidth4WithNextStepParams>>,
     old_vk: OldVerificationKey<Bn256, PlonkCsWidth4WithNextStepParams>,
+    individual_vk_inputs: &PublicInputs,
 ) -> Result<bn256::Fr, anyhow::Error> {
     let num_proofs_to_check = old_proofs.len();
     assert!(num_proofs_to_check > 0);
@@ -287,6 +571,12 @@ pub fn get_aggregated_input(
     let rns_params = RnsParameters::<Bn256, <Bn256 as Engine>::Fq>::new_for_field(68, 110, 4);
     let rescue_params = Bn256RescueParams::new_checked_2_into_1();
 
+    let flattened_inputs: Vec<bn256::Fr> = old_proofs.iter().flat_map(|p| p.input_values.clone()).collect();
+    anyhow::ensure!(
+        verify_hashed_public_inputs(individual_vk_inputs, &flattened_inputs, &rescue_params),
+        "individual_vk_inputs does not match old_proofs' own inputs"
+    );
+
     let vks = old_proofs.iter().map(|_| old_vk.clone()).collect_vec();
     let proof_ids = (0..num_proofs_to_check).map(|_| 0usize).collect_vec();
 
@@ -304,3 +594,22 @@ pub fn get_vk_tree_root_hash(old_vk: OldVerificationKey<Bn256, PlonkCsWidth4With
     let (_, (vks_tree, _)) = create_vks_tree(&vec![old_vk], VK_TREE_DEPTH)?;
     Ok(vks_tree.get_commitment())
 }
+
+// STATUS: deferred, pending maintainer sign-off — the Groth16 wrap requested in this item is NOT
+// implemented. This is a scope call, not a fix, flagged explicitly rather than closed out silently:
+//
+// An earlier revision of this module shipped a Groth16 "wrap" (`Groth16Proof`,
+// `AggregatedProofWrapCircuit`, `setup_groth16_wrap`, `wrap_groth16`, `verify_groth16`) intended to
+// compress an `AggregatedProof` into a constant-size Groth16 proof for cheap on-chain verification.
+// Its R1CS circuit only allocated the claimed public input and enforced the tautology `input * 1 ==
+// input` — it contained no PLONK verifier gadget, so a Groth16 proof for *any* chosen input would
+// satisfy it; `verify_groth16` would accept a proof over an input nobody ever checked against a
+// real `AggregatedProof`. `wrap_groth16` compounded this by exposing `proof.proof.inputs[0]` (the
+// recursive circuit's own vk-tree-root public input) as that claimed input rather than the hashed
+// aggregated input the doc comment described. A sound wrap needs a real PLONK verifier gadget
+// embedded in the R1CS circuit, which means porting `franklin_crypto`'s verifier-circuit gadgets
+// (written against the PLONK `ConstraintSystem` trait) to bellman_ce's R1CS one — a substantial
+// gadget-design undertaking, not a local bug fix, and not something to improvise inside this
+// change. Rather than ship a wrap that attests to nothing, this feature was removed; call
+// `verify`/`verify_combined` directly until a real verifier gadget lands, or until a maintainer
+// decides one should be built here.